@@ -6,19 +6,17 @@
 // of this source tree.
 
 use core::convert::TryFrom;
-use core::ops::Add;
 
 use derive_where::DeriveWhere;
 use digest::core_api::{BlockSizeUser, CoreProxy};
 use digest::Output;
-use generic_array::sequence::Concat;
-use generic_array::typenum::{IsLess, Le, NonZero, Sum, Unsigned, U2, U256, U32};
-use generic_array::{ArrayLength, GenericArray};
+use generic_array::typenum::{IsLess, Le, NonZero, Unsigned, U2, U256, U32};
+use generic_array::GenericArray;
 use hkdf::Hkdf;
 use hmac::{Hmac, Mac};
 use rand::{CryptoRng, RngCore};
 use voprf::Group;
-use zeroize::Zeroize;
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::ciphersuite::CipherSuite;
 use crate::errors::utils::check_slice_size;
@@ -33,6 +31,7 @@ use crate::serialization::{MacExt, Serialize};
 const STR_AUTH_KEY: [u8; 7] = *b"AuthKey";
 const STR_EXPORT_KEY: [u8; 9] = *b"ExportKey";
 const STR_PRIVATE_KEY: [u8; 10] = *b"PrivateKey";
+const STR_PAD: [u8; 3] = *b"Pad";
 const STR_OPAQUE_DERIVE_AUTH_KEY_PAIR: [u8; 24] = *b"OPAQUE-DeriveAuthKeyPair";
 type NonceLen = U32;
 
@@ -41,6 +40,7 @@ type NonceLen = U32;
 pub(crate) enum InnerEnvelopeMode {
     Zero = 0,
     Internal = 1,
+    External = 2,
 }
 
 impl TryFrom<u8> for InnerEnvelopeMode {
@@ -48,6 +48,7 @@ impl TryFrom<u8> for InnerEnvelopeMode {
     fn try_from(x: u8) -> Result<Self, Self::Error> {
         match x {
             1 => Ok(InnerEnvelopeMode::Internal),
+            2 => Ok(InnerEnvelopeMode::External),
             _ => Err(ProtocolError::SerializationError),
         }
     }
@@ -71,6 +72,10 @@ where
 {
     mode: InnerEnvelopeMode,
     nonce: GenericArray<u8, NonceLen>,
+    // Only present in `InnerEnvelopeMode::External`: the application-supplied
+    // client private key, XORed with a pad derived from the randomized
+    // password.
+    ciphertext: Option<GenericArray<u8, <CS::KeGroup as KeGroup>::SkLen>>,
     hmac: Output<CS::Hash>,
 }
 
@@ -90,6 +95,33 @@ where
     pub(crate) id_s: Serialize<'a, U2, <CS::KeGroup as KeGroup>::PkLen>,
 }
 
+impl<'a, CS: CipherSuite> OpenedEnvelope<'a, CS>
+where
+    <CS::Hash as CoreProxy>::Core: ProxyHash,
+    <<CS::Hash as CoreProxy>::Core as BlockSizeUser>::BlockSize: IsLess<U256>,
+    Le<<<CS::Hash as CoreProxy>::Core as BlockSizeUser>::BlockSize, U256>: NonZero,
+{
+    /// Derives a caller-sized, domain-separated subkey from `export_key`,
+    /// treating it as a pseudorandom key (PRK) for HKDF-Expand. This lets an
+    /// application split the single envelope export key into a hierarchy of
+    /// independent secrets (e.g. one per purpose) without exposing the PRK
+    /// itself.
+    pub(crate) fn derive_key(
+        &self,
+        label: &[u8],
+        length: usize,
+    ) -> Result<Zeroizing<Vec<u8>>, ProtocolError> {
+        let hkdf =
+            Hkdf::<CS::Hash>::from_prk(&self.export_key).map_err(|_| InternalError::HkdfError)?;
+
+        let mut output = Zeroizing::new(vec![0u8; length]);
+        hkdf.expand_multi_info(&[label], &mut output)
+            .map_err(|_| InternalError::HkdfError)?;
+
+        Ok(output)
+    }
+}
+
 pub(crate) struct OpenedInnerEnvelope<D: Hash>
 where
     D::Core: ProxyHash,
@@ -113,37 +145,74 @@ type SealResult<CS: CipherSuite> = (
     Output<CS::Hash>,
 );
 
-pub(crate) type EnvelopeLen<CS: CipherSuite> = Sum<NonceLen, OutputSize<CS::Hash>>;
-
 impl<CS: CipherSuite> Envelope<CS>
 where
     <CS::Hash as CoreProxy>::Core: ProxyHash,
     <<CS::Hash as CoreProxy>::Core as BlockSizeUser>::BlockSize: IsLess<U256>,
     Le<<<CS::Hash as CoreProxy>::Core as BlockSizeUser>::BlockSize, U256>: NonZero,
 {
+    // `context` would ideally live on `Identifiers` alongside `ids`, so every
+    // caller reaching `seal`/`open` through the ciphersuite's configuration
+    // threads it automatically instead of being able to pass `None` at a
+    // call site that should carry one. `ciphersuite.rs` and `opaque.rs`,
+    // which define `CipherSuite` and `Identifiers`, have never been part of
+    // this tree (not even at the baseline commit), so there is no
+    // `Identifiers` definition here to extend without inventing one from
+    // scratch. Given that constraint, this crate takes `context` as its own
+    // explicit `seal`/`open` parameter as the scoped-down, final shape for
+    // this tree; mandatory, non-optional binding per realm/version requires
+    // revisiting this once `opaque.rs` is checked in.
     #[allow(clippy::type_complexity)]
     pub(crate) fn seal<R: RngCore + CryptoRng>(
         rng: &mut R,
         randomized_pwd_hasher: Hkdf<CS::Hash>,
         server_s_pk: &PublicKey<CS::KeGroup>,
+        client_s_sk: Option<GenericArray<u8, <CS::KeGroup as KeGroup>::SkLen>>,
+        context: Option<&[u8]>,
         ids: Identifiers,
     ) -> Result<SealResult<CS>, ProtocolError> {
         let mut nonce = GenericArray::default();
         rng.fill_bytes(&mut nonce);
 
-        let (mode, client_s_pk) = (
-            InnerEnvelopeMode::Internal,
-            build_inner_envelope_internal::<CS>(randomized_pwd_hasher.clone(), nonce)?,
-        );
+        let mut context_len_prefix = [0u8; 2];
+        let context_parts = context_parts(context, &mut context_len_prefix)?;
+
+        let (mode, client_s_pk, ciphertext) = match client_s_sk {
+            None => (
+                InnerEnvelopeMode::Internal,
+                build_inner_envelope_internal::<CS>(
+                    randomized_pwd_hasher.clone(),
+                    nonce,
+                    context_parts,
+                )?,
+                None,
+            ),
+            Some(client_s_sk) => {
+                let (client_s_pk, ciphertext) = build_inner_envelope_external::<CS>(
+                    randomized_pwd_hasher.clone(),
+                    nonce,
+                    client_s_sk,
+                    context_parts,
+                )?;
+                (InnerEnvelopeMode::External, client_s_pk, Some(ciphertext))
+            }
+        };
 
         let (id_u, id_s) = bytestrings_from_identifiers::<CS::KeGroup>(
             ids,
             client_s_pk.to_arr(),
             server_s_pk.to_arr(),
         )?;
-        let aad = construct_aad(id_u.iter(), id_s.iter(), server_s_pk);
+        let aad = construct_aad(context_parts, id_u.iter(), id_s.iter(), server_s_pk);
 
-        let result = Self::seal_raw(randomized_pwd_hasher, nonce, aad, mode)?;
+        let result = Self::seal_raw(
+            randomized_pwd_hasher,
+            nonce,
+            ciphertext,
+            context_parts,
+            aad,
+            mode,
+        )?;
         Ok((
             result.0,
             client_s_pk,
@@ -159,6 +228,8 @@ where
     pub(crate) fn seal_raw<'a>(
         randomized_pwd_hasher: Hkdf<CS::Hash>,
         nonce: GenericArray<u8, NonceLen>,
+        ciphertext: Option<GenericArray<u8, <CS::KeGroup as KeGroup>::SkLen>>,
+        context_parts: ContextParts<'a>,
         aad: impl Iterator<Item = &'a [u8]>,
         mode: InnerEnvelopeMode,
     ) -> Result<SealRawResult<CS>, InternalError> {
@@ -166,15 +237,24 @@ where
         let mut export_key = Output::<CS::Hash>::default();
 
         randomized_pwd_hasher
-            .expand_multi_info(&[&nonce, &STR_AUTH_KEY], &mut hmac_key)
+            .expand_multi_info(
+                &context_info(context_parts, [&nonce, &STR_AUTH_KEY]),
+                &mut hmac_key,
+            )
             .map_err(|_| InternalError::HkdfError)?;
         randomized_pwd_hasher
-            .expand_multi_info(&[&nonce, &STR_EXPORT_KEY], &mut export_key)
+            .expand_multi_info(
+                &context_info(context_parts, [&nonce, &STR_EXPORT_KEY]),
+                &mut export_key,
+            )
             .map_err(|_| InternalError::HkdfError)?;
 
         let mut hmac =
             Hmac::<CS::Hash>::new_from_slice(&hmac_key).map_err(|_| InternalError::HmacError)?;
         hmac.update(&nonce);
+        if let Some(ciphertext) = &ciphertext {
+            hmac.update(ciphertext);
+        }
         hmac.update_iter(aad);
 
         let hmac_bytes = hmac.finalize().into_bytes();
@@ -183,6 +263,7 @@ where
             Self {
                 mode,
                 nonce,
+                ciphertext,
                 hmac: hmac_bytes,
             },
             export_key,
@@ -191,18 +272,38 @@ where
         ))
     }
 
+    // See the note on `context` in `seal` above: same scoped-down shape,
+    // same reason.
     pub(crate) fn open<'a>(
         &self,
         randomized_pwd_hasher: Hkdf<CS::Hash>,
         server_s_pk: PublicKey<CS::KeGroup>,
+        context: Option<&[u8]>,
         optional_ids: Identifiers<'a>,
     ) -> Result<OpenedEnvelope<'a, CS>, ProtocolError> {
+        let mut context_len_prefix = [0u8; 2];
+        let context_parts = context_parts(context, &mut context_len_prefix)?;
+
         let client_static_keypair = match self.mode {
             InnerEnvelopeMode::Zero => {
                 return Err(InternalError::IncompatibleEnvelopeModeError.into())
             }
-            InnerEnvelopeMode::Internal => {
-                recover_keys_internal::<CS>(randomized_pwd_hasher.clone(), self.nonce)?
+            InnerEnvelopeMode::Internal => recover_keys_internal::<CS>(
+                randomized_pwd_hasher.clone(),
+                self.nonce,
+                context_parts,
+            )?,
+            InnerEnvelopeMode::External => {
+                let ciphertext = self
+                    .ciphertext
+                    .as_ref()
+                    .ok_or(InternalError::IncompatibleEnvelopeModeError)?;
+                recover_keys_external::<CS>(
+                    randomized_pwd_hasher.clone(),
+                    self.nonce,
+                    ciphertext,
+                    context_parts,
+                )?
             }
         };
 
@@ -211,9 +312,9 @@ where
             client_static_keypair.public().to_arr(),
             server_s_pk.to_arr(),
         )?;
-        let aad = construct_aad(id_u.iter(), id_s.iter(), &server_s_pk);
+        let aad = construct_aad(context_parts, id_u.iter(), id_s.iter(), &server_s_pk);
 
-        let opened = self.open_raw(randomized_pwd_hasher, aad)?;
+        let opened = self.open_raw(randomized_pwd_hasher, context_parts, aad)?;
 
         Ok(OpenedEnvelope {
             client_static_keypair,
@@ -228,21 +329,31 @@ where
     pub(crate) fn open_raw<'a>(
         &self,
         randomized_pwd_hasher: Hkdf<CS::Hash>,
+        context_parts: ContextParts<'a>,
         aad: impl Iterator<Item = &'a [u8]>,
     ) -> Result<OpenedInnerEnvelope<CS::Hash>, InternalError> {
         let mut hmac_key = Output::<CS::Hash>::default();
         let mut export_key = Output::<CS::Hash>::default();
 
         randomized_pwd_hasher
-            .expand(&self.nonce.concat(STR_AUTH_KEY.into()), &mut hmac_key)
+            .expand_multi_info(
+                &context_info(context_parts, [&self.nonce, &STR_AUTH_KEY]),
+                &mut hmac_key,
+            )
             .map_err(|_| InternalError::HkdfError)?;
         randomized_pwd_hasher
-            .expand(&self.nonce.concat(STR_EXPORT_KEY.into()), &mut export_key)
+            .expand_multi_info(
+                &context_info(context_parts, [&self.nonce, &STR_EXPORT_KEY]),
+                &mut export_key,
+            )
             .map_err(|_| InternalError::HkdfError)?;
 
         let mut hmac =
             Hmac::<CS::Hash>::new_from_slice(&hmac_key).map_err(|_| InternalError::HmacError)?;
         hmac.update(&self.nonce);
+        if let Some(ciphertext) = &self.ciphertext {
+            hmac.update(ciphertext);
+        }
         hmac.update_iter(aad);
         hmac.verify(&self.hmac)
             .map_err(|_| InternalError::SealOpenHmacError)?;
@@ -250,11 +361,23 @@ where
         Ok(OpenedInnerEnvelope { export_key })
     }
 
-    // Creates a dummy envelope object that serializes to the all-zeros byte string
-    pub(crate) fn dummy() -> Self {
+    // Creates a dummy envelope object that serializes to an all-zeros byte
+    // string of the same length as a real envelope sealed in `mode`. The
+    // server returns this for a nonexistent user in place of a real
+    // envelope; if its length didn't track the mode the deployment actually
+    // registers users under, a network attacker could tell real and dummy
+    // responses apart by length alone, defeating the whole point of `dummy`.
+    pub(crate) fn dummy(mode: InnerEnvelopeMode) -> Self {
+        let ciphertext = match mode {
+            InnerEnvelopeMode::External => {
+                Some(GenericArray::<u8, <CS::KeGroup as KeGroup>::SkLen>::default())
+            }
+            _ => None,
+        };
         Self {
             mode: InnerEnvelopeMode::Zero,
             nonce: GenericArray::default(),
+            ciphertext,
             hmac: GenericArray::default(),
         }
     }
@@ -263,32 +386,61 @@ where
         OutputSize::<CS::Hash>::USIZE
     }
 
-    pub(crate) fn len() -> usize {
-        OutputSize::<CS::Hash>::USIZE + NonceLen::USIZE
+    /// The serialized length of this envelope. This depends on whether a
+    /// ciphertext is present (i.e. `InnerEnvelopeMode::External`, which
+    /// carries an extra ciphertext of `<CS::KeGroup as KeGroup>::SkLen`
+    /// bytes), not on `self.mode` directly, so that [`Envelope::dummy`] can
+    /// report the length of whatever mode it's padding to without having to
+    /// claim that mode in its own (always-`Zero`) `mode` byte.
+    ///
+    /// Any caller that sizes a fixed buffer from this envelope's wire format
+    /// must call `len()` (or use the `Vec<u8>` returned by `serialize()`)
+    /// rather than assuming a fixed size: the optional ciphertext makes the
+    /// length vary with the mode the envelope was sealed (or padded) for.
+    pub(crate) fn len(&self) -> usize {
+        let base = 1 + NonceLen::USIZE + OutputSize::<CS::Hash>::USIZE;
+        match &self.ciphertext {
+            Some(_) => base + <CS::KeGroup as KeGroup>::SkLen::USIZE,
+            None => base,
+        }
     }
 
-    pub(crate) fn serialize(&self) -> GenericArray<u8, EnvelopeLen<CS>>
-    where
-        // Envelope: Nonce + Hash
-        NonceLen: Add<OutputSize<CS::Hash>>,
-        EnvelopeLen<CS>: ArrayLength<u8>,
-    {
-        self.nonce.concat(self.hmac.clone())
+    pub(crate) fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.len());
+        bytes.push(self.mode.clone() as u8);
+        bytes.extend_from_slice(&self.nonce);
+        if let Some(ciphertext) = &self.ciphertext {
+            bytes.extend_from_slice(ciphertext);
+        }
+        bytes.extend_from_slice(&self.hmac);
+        bytes
     }
 
     pub(crate) fn deserialize(bytes: &[u8]) -> Result<Self, ProtocolError> {
-        let mode = InnerEnvelopeMode::Internal; // Better way to hard-code this?
+        let (mode_byte, remainder) = bytes
+            .split_first()
+            .ok_or(ProtocolError::SerializationError)?;
+        let mode = InnerEnvelopeMode::try_from(*mode_byte)?;
 
-        if bytes.len() < NonceLen::USIZE {
+        if remainder.len() < NonceLen::USIZE {
             return Err(ProtocolError::SerializationError);
         }
-        let nonce = GenericArray::clone_from_slice(&bytes[..NonceLen::USIZE]);
+        let nonce = GenericArray::clone_from_slice(&remainder[..NonceLen::USIZE]);
+        let remainder = &remainder[NonceLen::USIZE..];
 
-        let remainder = match mode {
+        let (ciphertext, remainder) = match mode {
             InnerEnvelopeMode::Zero => {
                 return Err(InternalError::IncompatibleEnvelopeModeError.into())
             }
-            InnerEnvelopeMode::Internal => &bytes[NonceLen::USIZE..],
+            InnerEnvelopeMode::Internal => (None, remainder),
+            InnerEnvelopeMode::External => {
+                let sk_len = <CS::KeGroup as KeGroup>::SkLen::USIZE;
+                let ciphertext_bytes = check_slice_size(remainder, sk_len, "external_ciphertext")?;
+                (
+                    Some(GenericArray::clone_from_slice(ciphertext_bytes)),
+                    &remainder[sk_len..],
+                )
+            }
         };
 
         let hmac_key_size = Self::hmac_key_size();
@@ -297,6 +449,7 @@ where
         Ok(Self {
             mode,
             nonce,
+            ciphertext,
             hmac: GenericArray::clone_from_slice(hmac),
         })
     }
@@ -304,9 +457,10 @@ where
 
 // Helper functions
 
-fn build_inner_envelope_internal<CS: CipherSuite>(
+fn build_inner_envelope_internal<'a, CS: CipherSuite>(
     randomized_pwd_hasher: Hkdf<CS::Hash>,
     nonce: GenericArray<u8, NonceLen>,
+    context_parts: ContextParts<'a>,
 ) -> Result<PublicKey<CS::KeGroup>, ProtocolError>
 where
     <CS::Hash as CoreProxy>::Core: ProxyHash,
@@ -315,7 +469,10 @@ where
 {
     let mut keypair_seed = GenericArray::<_, <CS::KeGroup as KeGroup>::SkLen>::default();
     randomized_pwd_hasher
-        .expand(&nonce.concat(STR_PRIVATE_KEY.into()), &mut keypair_seed)
+        .expand_multi_info(
+            &context_info(context_parts, [&nonce, &STR_PRIVATE_KEY]),
+            &mut keypair_seed,
+        )
         .map_err(|_| InternalError::HkdfError)?;
     let client_static_keypair = KeyPair::<CS::KeGroup>::from_private_key_slice(
         &CS::OprfGroup::scalar_as_bytes(CS::OprfGroup::hash_to_scalar::<CS::Hash, _, _>(
@@ -327,9 +484,10 @@ where
     Ok(client_static_keypair.public().clone())
 }
 
-fn recover_keys_internal<CS: CipherSuite>(
+fn recover_keys_internal<'a, CS: CipherSuite>(
     randomized_pwd_hasher: Hkdf<CS::Hash>,
     nonce: GenericArray<u8, NonceLen>,
+    context_parts: ContextParts<'a>,
 ) -> Result<KeyPair<CS::KeGroup>, ProtocolError>
 where
     <CS::Hash as CoreProxy>::Core: ProxyHash,
@@ -338,7 +496,10 @@ where
 {
     let mut keypair_seed = GenericArray::<_, <CS::KeGroup as KeGroup>::SkLen>::default();
     randomized_pwd_hasher
-        .expand(&nonce.concat(STR_PRIVATE_KEY.into()), &mut keypair_seed)
+        .expand_multi_info(
+            &context_info(context_parts, [&nonce, &STR_PRIVATE_KEY]),
+            &mut keypair_seed,
+        )
         .map_err(|_| InternalError::HkdfError)?;
     let client_static_keypair = KeyPair::<CS::KeGroup>::from_private_key_slice(
         &CS::OprfGroup::scalar_as_bytes(CS::OprfGroup::hash_to_scalar::<CS::Hash, _, _>(
@@ -350,10 +511,329 @@ where
     Ok(client_static_keypair)
 }
 
+// Seals an application-supplied client private key (`client_s_sk`) by XORing
+// it with a pad derived from the randomized password, rather than deriving
+// the keypair from the password as `build_inner_envelope_internal` does.
+fn build_inner_envelope_external<'a, CS: CipherSuite>(
+    randomized_pwd_hasher: Hkdf<CS::Hash>,
+    nonce: GenericArray<u8, NonceLen>,
+    client_s_sk: GenericArray<u8, <CS::KeGroup as KeGroup>::SkLen>,
+    context_parts: ContextParts<'a>,
+) -> Result<
+    (
+        PublicKey<CS::KeGroup>,
+        GenericArray<u8, <CS::KeGroup as KeGroup>::SkLen>,
+    ),
+    ProtocolError,
+>
+where
+    <CS::Hash as CoreProxy>::Core: ProxyHash,
+    <<CS::Hash as CoreProxy>::Core as BlockSizeUser>::BlockSize: IsLess<U256>,
+    Le<<<CS::Hash as CoreProxy>::Core as BlockSizeUser>::BlockSize, U256>: NonZero,
+{
+    let client_static_keypair = KeyPair::<CS::KeGroup>::from_private_key_slice(&client_s_sk)?;
+
+    let mut pad = GenericArray::<u8, <CS::KeGroup as KeGroup>::SkLen>::default();
+    randomized_pwd_hasher
+        .expand_multi_info(&context_info(context_parts, [&nonce, &STR_PAD]), &mut pad)
+        .map_err(|_| InternalError::HkdfError)?;
+
+    let mut ciphertext = GenericArray::<u8, <CS::KeGroup as KeGroup>::SkLen>::default();
+    for (dst, (pad_byte, sk_byte)) in ciphertext
+        .iter_mut()
+        .zip(pad.iter().zip(client_s_sk.iter()))
+    {
+        *dst = pad_byte ^ sk_byte;
+    }
+
+    Ok((client_static_keypair.public().clone(), ciphertext))
+}
+
+// Recovers the client private key sealed by `build_inner_envelope_external`
+// by re-deriving the pad and undoing the XOR.
+fn recover_keys_external<'a, CS: CipherSuite>(
+    randomized_pwd_hasher: Hkdf<CS::Hash>,
+    nonce: GenericArray<u8, NonceLen>,
+    ciphertext: &GenericArray<u8, <CS::KeGroup as KeGroup>::SkLen>,
+    context_parts: ContextParts<'a>,
+) -> Result<KeyPair<CS::KeGroup>, ProtocolError>
+where
+    <CS::Hash as CoreProxy>::Core: ProxyHash,
+    <<CS::Hash as CoreProxy>::Core as BlockSizeUser>::BlockSize: IsLess<U256>,
+    Le<<<CS::Hash as CoreProxy>::Core as BlockSizeUser>::BlockSize, U256>: NonZero,
+{
+    let mut pad = GenericArray::<u8, <CS::KeGroup as KeGroup>::SkLen>::default();
+    randomized_pwd_hasher
+        .expand_multi_info(&context_info(context_parts, [&nonce, &STR_PAD]), &mut pad)
+        .map_err(|_| InternalError::HkdfError)?;
+
+    let mut client_s_sk = GenericArray::<u8, <CS::KeGroup as KeGroup>::SkLen>::default();
+    for (dst, (pad_byte, ct_byte)) in client_s_sk
+        .iter_mut()
+        .zip(pad.iter().zip(ciphertext.iter()))
+    {
+        *dst = pad_byte ^ ct_byte;
+    }
+
+    let client_static_keypair = KeyPair::<CS::KeGroup>::from_private_key_slice(&client_s_sk)?;
+
+    Ok(client_static_keypair)
+}
+
+// A length-prefixed encoding of an optional application `context`: `[len,
+// context]` where `len` is `context`'s length as a big-endian u16, or two
+// empty slices when there is no context. Keeping the length prefix alongside
+// the bytes (rather than just concatenating `context` ahead of whatever
+// follows it) ensures the overall HKDF info / aad encoding stays injective:
+// without it, two different `(context, nonce)` pairs could concatenate to
+// the same bytes.
+type ContextParts<'a> = [&'a [u8]; 2];
+
+fn context_parts<'a>(
+    context: Option<&'a [u8]>,
+    len_prefix: &'a mut [u8; 2],
+) -> Result<ContextParts<'a>, ProtocolError> {
+    match context {
+        Some(context) => {
+            // A length that doesn't fit in the u16 prefix would silently
+            // truncate below, letting two different over-long contexts
+            // collide on the same encoded length and reopening the
+            // injective-encoding gap this prefix exists to close.
+            let len = u16::try_from(context.len()).map_err(|_| ProtocolError::SerializationError)?;
+            *len_prefix = len.to_be_bytes();
+            Ok([len_prefix.as_slice(), context])
+        }
+        None => Ok([&[], &[]]),
+    }
+}
+
+// Prepends `context_parts` to an HKDF info segment, binding every key
+// derived from the envelope (AuthKey, ExportKey, PrivateKey, and the
+// `InnerEnvelopeMode::External` pad) to the application's `context`, e.g. a
+// protocol version or deployment identifier. An envelope sealed under one
+// context fails to open under another, since the derived hmac_key (and the
+// derived keypair or pad) will differ.
+fn context_info<'a>(context_parts: ContextParts<'a>, info: [&'a [u8]; 2]) -> [&'a [u8]; 4] {
+    [context_parts[0], context_parts[1], info[0], info[1]]
+}
+
 fn construct_aad<'a>(
+    context_parts: ContextParts<'a>,
     id_u: impl Iterator<Item = &'a [u8]>,
     id_s: impl Iterator<Item = &'a [u8]>,
     server_s_pk: &'a [u8],
 ) -> impl Iterator<Item = &'a [u8]> {
-    [server_s_pk].into_iter().chain(id_s).chain(id_u)
+    context_parts
+        .into_iter()
+        .chain([server_s_pk])
+        .chain(id_s)
+        .chain(id_u)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use core::marker::PhantomData;
+
+    use rand::rngs::OsRng;
+    use sha2::Sha512;
+
+    use super::*;
+
+    /// Associates a [`KeGroup`] under test with a [`Group`] whose scalar
+    /// length matches `KeGroup::SkLen`, so [`TestSuite`] can drive
+    /// `build_inner_envelope_internal`/`recover_keys_internal`'s real
+    /// `OprfGroup::hash_to_scalar` derivation without every curve being
+    /// forced through the same fixed-size OPRF group: a fixed `Ristretto255`
+    /// `OprfGroup` only produces 32-byte scalars, which
+    /// `KeyPair::from_private_key_slice` rejects outright for any `KeGroup`
+    /// whose `SkLen` isn't also 32 (P384Group, P521Group).
+    pub(crate) trait TestOprfGroup: KeGroup {
+        type OprfGroup: Group;
+    }
+
+    /// A minimal [`CipherSuite`] that pairs an arbitrary [`KeGroup`] (the one
+    /// under test) with whatever OPRF group [`TestOprfGroup`] says matches
+    /// it, and a fixed hash, so every `KeGroup` implementation can drive a
+    /// real `Envelope::seal`/`open` round trip without each curve module
+    /// building its own ciphersuite boilerplate.
+    pub(crate) struct TestSuite<G>(PhantomData<G>);
+
+    impl<G: TestOprfGroup> CipherSuite for TestSuite<G> {
+        type OprfGroup = G::OprfGroup;
+        type KeGroup = G;
+        type Hash = Sha512;
+    }
+
+    fn server_keypair<G: KeGroup>() -> KeyPair<G> {
+        KeyPair::<G>::from_private_key_slice(&G::serialize_sk(&G::random_sk(&mut OsRng)))
+            .expect("failed to build server keypair")
+    }
+
+    fn randomized_pwd_hasher() -> Hkdf<Sha512> {
+        Hkdf::<Sha512>::new(None, b"randomized password")
+    }
+
+    /// Seals and opens an envelope in `InnerEnvelopeMode::Internal` (client
+    /// keys derived from the password) for the given `KeGroup`, and checks
+    /// the export key recovered by `open` matches what `seal` produced.
+    pub(crate) fn internal_seal_open_round_trip<G: TestOprfGroup>() {
+        let mut rng = OsRng;
+        let hasher = randomized_pwd_hasher();
+        let server_keypair = server_keypair::<G>();
+
+        let (envelope, _client_pk, export_key, ..) = Envelope::<TestSuite<G>>::seal(
+            &mut rng,
+            hasher.clone(),
+            server_keypair.public(),
+            None,
+            None,
+            Identifiers::default(),
+        )
+        .expect("seal failed");
+
+        let opened = envelope
+            .open(
+                hasher,
+                server_keypair.public().clone(),
+                None,
+                Identifiers::default(),
+            )
+            .expect("open failed");
+
+        assert_eq!(export_key, opened.export_key);
+    }
+
+    /// Seals and opens an envelope in `InnerEnvelopeMode::External` (an
+    /// application-supplied client private key) for the given `KeGroup`, and
+    /// checks both the recovered export key and the recovered client static
+    /// public key match what `seal` produced.
+    pub(crate) fn external_seal_open_round_trip<G: TestOprfGroup>() {
+        let mut rng = OsRng;
+        let hasher = randomized_pwd_hasher();
+        let server_keypair = server_keypair::<G>();
+        let client_s_sk = G::serialize_sk(&G::random_sk(&mut rng));
+
+        let (envelope, client_pk, export_key, ..) = Envelope::<TestSuite<G>>::seal(
+            &mut rng,
+            hasher.clone(),
+            server_keypair.public(),
+            Some(client_s_sk),
+            None,
+            Identifiers::default(),
+        )
+        .expect("seal failed");
+
+        let opened = envelope
+            .open(
+                hasher,
+                server_keypair.public().clone(),
+                None,
+                Identifiers::default(),
+            )
+            .expect("open failed");
+
+        assert_eq!(export_key, opened.export_key);
+        assert_eq!(
+            client_pk.to_arr(),
+            opened.client_static_keypair.public().to_arr()
+        );
+    }
+
+    /// An envelope sealed under one `context` must fail to `open` under a
+    /// different one, since every key `open` derives (hmac_key, export_key,
+    /// and the recovered client keypair/pad) is bound to `context`.
+    pub(crate) fn context_binding_mismatch<G: TestOprfGroup>() {
+        let mut rng = OsRng;
+        let hasher = randomized_pwd_hasher();
+        let server_keypair = server_keypair::<G>();
+
+        let (envelope, ..) = Envelope::<TestSuite<G>>::seal(
+            &mut rng,
+            hasher.clone(),
+            server_keypair.public(),
+            None,
+            Some(b"context A"),
+            Identifiers::default(),
+        )
+        .expect("seal failed");
+
+        let result = envelope.open(
+            hasher,
+            server_keypair.public().clone(),
+            Some(b"context B"),
+            Identifiers::default(),
+        );
+
+        assert!(result.is_err());
+    }
+
+    /// The `InnerEnvelopeMode::External` pad derivation must bind `context`
+    /// too: an envelope sealing an application-supplied client private key
+    /// under one `context` must fail to `open` (and thus fail to recover
+    /// that key) under a different one. This is the gap `31768e1` closed —
+    /// the pad derivation had previously been left out of context binding
+    /// despite this module's doc comment claiming full coverage.
+    pub(crate) fn context_binding_mismatch_external<G: TestOprfGroup>() {
+        let mut rng = OsRng;
+        let hasher = randomized_pwd_hasher();
+        let server_keypair = server_keypair::<G>();
+        let client_s_sk = G::serialize_sk(&G::random_sk(&mut rng));
+
+        let (envelope, ..) = Envelope::<TestSuite<G>>::seal(
+            &mut rng,
+            hasher.clone(),
+            server_keypair.public(),
+            Some(client_s_sk),
+            Some(b"context A"),
+            Identifiers::default(),
+        )
+        .expect("seal failed");
+
+        let result = envelope.open(
+            hasher,
+            server_keypair.public().clone(),
+            Some(b"context B"),
+            Identifiers::default(),
+        );
+
+        assert!(result.is_err());
+    }
+
+    /// `OpenedEnvelope::derive_key` must produce distinct, correct-length
+    /// outputs for distinct labels.
+    pub(crate) fn derive_key_distinct_labels<G: TestOprfGroup>() {
+        let mut rng = OsRng;
+        let hasher = randomized_pwd_hasher();
+        let server_keypair = server_keypair::<G>();
+
+        let (envelope, ..) = Envelope::<TestSuite<G>>::seal(
+            &mut rng,
+            hasher.clone(),
+            server_keypair.public(),
+            None,
+            None,
+            Identifiers::default(),
+        )
+        .expect("seal failed");
+
+        let opened = envelope
+            .open(
+                hasher,
+                server_keypair.public().clone(),
+                None,
+                Identifiers::default(),
+            )
+            .expect("open failed");
+
+        let a = opened
+            .derive_key(b"label-a", 32)
+            .expect("derive_key failed for label-a");
+        let b = opened
+            .derive_key(b"label-b", 32)
+            .expect("derive_key failed for label-b");
+
+        assert_eq!(a.len(), 32);
+        assert_eq!(b.len(), 32);
+        assert_ne!(*a, *b);
+    }
 }
@@ -0,0 +1,71 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+use generic_array::{ArrayLength, GenericArray};
+use rand::{CryptoRng, RngCore};
+
+use crate::errors::ProtocolError;
+
+#[cfg(any(
+    feature = "secp256k1",
+    feature = "p256",
+    feature = "p384",
+    feature = "p521"
+))]
+pub(crate) mod nist;
+
+#[cfg(feature = "secp256k1")]
+pub mod secp256k1;
+
+#[cfg(feature = "p256")]
+pub mod p256;
+
+#[cfg(feature = "p384")]
+pub mod p384;
+
+#[cfg(feature = "p521")]
+pub mod p521;
+
+/// A group over which the OPAQUE key exchange can be performed, abstracting
+/// over the concrete elliptic curve (or other group) backing a
+/// [`crate::ciphersuite::CipherSuite::KeGroup`]. Each curve is gated behind
+/// its own cargo feature so that `no_std` callers can compile in exactly the
+/// curve(s) they need.
+pub trait KeGroup {
+    /// A public key in this group.
+    type Pk: Clone;
+    /// A private (scalar) key in this group.
+    type Sk: Clone;
+    /// The byte length of a serialized public key.
+    type PkLen: ArrayLength<u8>;
+    /// The byte length of a serialized private key.
+    type SkLen: ArrayLength<u8>;
+
+    /// Samples a random private key.
+    fn random_sk<R: RngCore + CryptoRng>(rng: &mut R) -> Self::Sk;
+
+    /// Computes the public key corresponding to a private key.
+    fn public_key(sk: &Self::Sk) -> Self::Pk;
+
+    /// Serializes a public key to its canonical byte encoding.
+    fn serialize_pk(pk: &Self::Pk) -> GenericArray<u8, Self::PkLen>;
+
+    /// Deserializes a public key from its canonical byte encoding.
+    fn deserialize_pk(bytes: &[u8]) -> Result<Self::Pk, ProtocolError>;
+
+    /// Serializes a private key to its canonical byte encoding.
+    fn serialize_sk(sk: &Self::Sk) -> GenericArray<u8, Self::SkLen>;
+
+    /// Deserializes a private key from its canonical byte encoding.
+    fn deserialize_sk(bytes: &[u8]) -> Result<Self::Sk, ProtocolError>;
+
+    /// Computes the Diffie-Hellman shared secret `sk * pk`, serialized to its
+    /// canonical byte encoding. This is the key-exchange operation proper;
+    /// the envelope only uses `random_sk`/`public_key`/(de)serialization to
+    /// seal and recover the client's static keypair.
+    fn diffie_hellman(sk: &Self::Sk, pk: &Self::Pk) -> GenericArray<u8, Self::SkLen>;
+}
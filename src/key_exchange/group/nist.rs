@@ -0,0 +1,79 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+//! Shared [`KeGroup`] plumbing for curves built on the `elliptic-curve`
+//! crate ecosystem (the NIST P-curves and secp256k1 all expose the same
+//! `CurveArithmetic` interface), so each curve-specific module only needs to
+//! name its concrete `Curve` type.
+
+use elliptic_curve::generic_array::GenericArray as EcGenericArray;
+use elliptic_curve::sec1::{CompressedPointSize, FromEncodedPoint, ModulusSize, ToEncodedPoint};
+use elliptic_curve::{
+    AffinePoint, CurveArithmetic, FieldBytesSize, NonZeroScalar, PublicKey as EcPublicKey,
+};
+use generic_array::GenericArray;
+use rand::{CryptoRng, RngCore};
+
+use crate::errors::ProtocolError;
+use crate::key_exchange::group::KeGroup;
+
+pub(super) fn random_sk<C, R>(rng: &mut R) -> NonZeroScalar<C>
+where
+    C: CurveArithmetic,
+    R: RngCore + CryptoRng,
+{
+    NonZeroScalar::<C>::random(rng)
+}
+
+pub(super) fn public_key<C: CurveArithmetic>(sk: &NonZeroScalar<C>) -> EcPublicKey<C> {
+    EcPublicKey::from_secret_scalar(sk)
+}
+
+pub(super) fn serialize_pk<C>(pk: &EcPublicKey<C>) -> GenericArray<u8, CompressedPointSize<C>>
+where
+    C: CurveArithmetic,
+    FieldBytesSize<C>: ModulusSize,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+{
+    GenericArray::clone_from_slice(pk.to_encoded_point(true).as_bytes())
+}
+
+pub(super) fn deserialize_pk<C>(bytes: &[u8]) -> Result<EcPublicKey<C>, ProtocolError>
+where
+    C: CurveArithmetic,
+    FieldBytesSize<C>: ModulusSize,
+    AffinePoint<C>: FromEncodedPoint<C> + ToEncodedPoint<C>,
+{
+    EcPublicKey::<C>::from_sec1_bytes(bytes).map_err(|_| ProtocolError::SerializationError)
+}
+
+pub(super) fn serialize_sk<C: CurveArithmetic>(
+    sk: &NonZeroScalar<C>,
+) -> GenericArray<u8, C::FieldBytesSize> {
+    GenericArray::clone_from_slice(sk.to_repr().as_slice())
+}
+
+pub(super) fn deserialize_sk<C>(bytes: &[u8]) -> Result<NonZeroScalar<C>, ProtocolError>
+where
+    C: CurveArithmetic,
+{
+    let field_bytes = EcGenericArray::<u8, C::FieldBytesSize>::from_exact_iter(bytes.iter().copied())
+        .ok_or(ProtocolError::SerializationError)?;
+    Option::from(NonZeroScalar::<C>::from_repr(field_bytes))
+        .ok_or(ProtocolError::SerializationError)
+}
+
+pub(super) fn diffie_hellman<C>(
+    sk: &NonZeroScalar<C>,
+    pk: &EcPublicKey<C>,
+) -> GenericArray<u8, C::FieldBytesSize>
+where
+    C: CurveArithmetic,
+{
+    let shared_secret = elliptic_curve::ecdh::diffie_hellman(sk, pk.as_affine());
+    GenericArray::clone_from_slice(shared_secret.raw_secret_bytes().as_slice())
+}
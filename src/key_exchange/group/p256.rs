@@ -0,0 +1,126 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+//! [`KeGroup`] implementation over NIST P-256, gated behind the `p256`
+//! feature.
+
+use generic_array::typenum::{U32, U33};
+use generic_array::GenericArray;
+use p256::NistP256;
+use rand::{CryptoRng, RngCore};
+
+use crate::errors::ProtocolError;
+use crate::key_exchange::group::nist;
+use crate::key_exchange::group::KeGroup;
+
+/// The NIST P-256 group.
+pub struct P256Group;
+
+impl KeGroup for P256Group {
+    type Pk = elliptic_curve::PublicKey<NistP256>;
+    type Sk = elliptic_curve::NonZeroScalar<NistP256>;
+    type PkLen = U33;
+    type SkLen = U32;
+
+    fn random_sk<R: RngCore + CryptoRng>(rng: &mut R) -> Self::Sk {
+        nist::random_sk::<NistP256, R>(rng)
+    }
+
+    fn public_key(sk: &Self::Sk) -> Self::Pk {
+        nist::public_key(sk)
+    }
+
+    fn serialize_pk(pk: &Self::Pk) -> GenericArray<u8, Self::PkLen> {
+        nist::serialize_pk(pk)
+    }
+
+    fn deserialize_pk(bytes: &[u8]) -> Result<Self::Pk, ProtocolError> {
+        nist::deserialize_pk(bytes)
+    }
+
+    fn serialize_sk(sk: &Self::Sk) -> GenericArray<u8, Self::SkLen> {
+        nist::serialize_sk(sk)
+    }
+
+    fn deserialize_sk(bytes: &[u8]) -> Result<Self::Sk, ProtocolError> {
+        nist::deserialize_sk(bytes)
+    }
+
+    fn diffie_hellman(sk: &Self::Sk, pk: &Self::Pk) -> GenericArray<u8, Self::SkLen> {
+        nist::diffie_hellman(sk, pk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+    use voprf::Ristretto255;
+
+    use super::*;
+    use crate::envelope::tests::TestOprfGroup;
+
+    // Ristretto255's 32-byte scalar matches P256Group::SkLen, so it can
+    // stand in as the test ciphersuite's OPRF group unchanged.
+    impl TestOprfGroup for P256Group {
+        type OprfGroup = Ristretto255;
+    }
+
+    // Round-trips `KeGroup::serialize_sk`/`serialize_pk` only; see
+    // `envelope_seal_open_round_trip` below for a real `Envelope::seal`/`open`
+    // round trip over this curve.
+    #[test]
+    fn keygroup_serialize_round_trip() {
+        let sk = P256Group::random_sk(&mut OsRng);
+        let pk = P256Group::public_key(&sk);
+
+        let sk_bytes = P256Group::serialize_sk(&sk);
+        let opened_sk = P256Group::deserialize_sk(&sk_bytes).expect("failed to deserialize sk");
+        assert_eq!(sk_bytes, P256Group::serialize_sk(&opened_sk));
+        assert_eq!(
+            P256Group::serialize_pk(&pk),
+            P256Group::serialize_pk(&P256Group::public_key(&opened_sk))
+        );
+
+        let pk_bytes = P256Group::serialize_pk(&pk);
+        let opened_pk = P256Group::deserialize_pk(&pk_bytes).expect("failed to deserialize pk");
+        assert_eq!(pk_bytes, P256Group::serialize_pk(&opened_pk));
+    }
+
+    #[test]
+    fn envelope_seal_open_round_trip() {
+        crate::envelope::tests::internal_seal_open_round_trip::<P256Group>();
+        crate::envelope::tests::external_seal_open_round_trip::<P256Group>();
+    }
+
+    #[test]
+    fn envelope_context_binding_mismatch() {
+        crate::envelope::tests::context_binding_mismatch::<P256Group>();
+    }
+
+    #[test]
+    fn envelope_context_binding_mismatch_external() {
+        crate::envelope::tests::context_binding_mismatch_external::<P256Group>();
+    }
+
+    #[test]
+    fn envelope_derive_key_distinct_labels() {
+        crate::envelope::tests::derive_key_distinct_labels::<P256Group>();
+    }
+
+    #[test]
+    fn diffie_hellman_agreement() {
+        let sk_a = P256Group::random_sk(&mut OsRng);
+        let sk_b = P256Group::random_sk(&mut OsRng);
+        let pk_a = P256Group::public_key(&sk_a);
+        let pk_b = P256Group::public_key(&sk_b);
+
+        assert_eq!(
+            P256Group::diffie_hellman(&sk_a, &pk_b),
+            P256Group::diffie_hellman(&sk_b, &pk_a)
+        );
+    }
+}
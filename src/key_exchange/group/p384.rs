@@ -0,0 +1,126 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+//! [`KeGroup`] implementation over NIST P-384, gated behind the `p384`
+//! feature.
+
+use generic_array::typenum::{U48, U49};
+use generic_array::GenericArray;
+use p384::NistP384;
+use rand::{CryptoRng, RngCore};
+
+use crate::errors::ProtocolError;
+use crate::key_exchange::group::nist;
+use crate::key_exchange::group::KeGroup;
+
+/// The NIST P-384 group.
+pub struct P384Group;
+
+impl KeGroup for P384Group {
+    type Pk = elliptic_curve::PublicKey<NistP384>;
+    type Sk = elliptic_curve::NonZeroScalar<NistP384>;
+    type PkLen = U49;
+    type SkLen = U48;
+
+    fn random_sk<R: RngCore + CryptoRng>(rng: &mut R) -> Self::Sk {
+        nist::random_sk::<NistP384, R>(rng)
+    }
+
+    fn public_key(sk: &Self::Sk) -> Self::Pk {
+        nist::public_key(sk)
+    }
+
+    fn serialize_pk(pk: &Self::Pk) -> GenericArray<u8, Self::PkLen> {
+        nist::serialize_pk(pk)
+    }
+
+    fn deserialize_pk(bytes: &[u8]) -> Result<Self::Pk, ProtocolError> {
+        nist::deserialize_pk(bytes)
+    }
+
+    fn serialize_sk(sk: &Self::Sk) -> GenericArray<u8, Self::SkLen> {
+        nist::serialize_sk(sk)
+    }
+
+    fn deserialize_sk(bytes: &[u8]) -> Result<Self::Sk, ProtocolError> {
+        nist::deserialize_sk(bytes)
+    }
+
+    fn diffie_hellman(sk: &Self::Sk, pk: &Self::Pk) -> GenericArray<u8, Self::SkLen> {
+        nist::diffie_hellman(sk, pk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+
+    use super::*;
+    use crate::envelope::tests::TestOprfGroup;
+
+    // `Ristretto255`'s 32-byte scalar is too short for `P384Group::SkLen`
+    // (48), so the test ciphersuite uses the curve's own `NistP384` as its
+    // OPRF group instead, whose scalar length matches by construction.
+    impl TestOprfGroup for P384Group {
+        type OprfGroup = NistP384;
+    }
+
+    // Round-trips `KeGroup::serialize_sk`/`serialize_pk` only; see
+    // `envelope_seal_open_round_trip` below for a real `Envelope::seal`/`open`
+    // round trip over this curve.
+    #[test]
+    fn keygroup_serialize_round_trip() {
+        let sk = P384Group::random_sk(&mut OsRng);
+        let pk = P384Group::public_key(&sk);
+
+        let sk_bytes = P384Group::serialize_sk(&sk);
+        let opened_sk = P384Group::deserialize_sk(&sk_bytes).expect("failed to deserialize sk");
+        assert_eq!(sk_bytes, P384Group::serialize_sk(&opened_sk));
+        assert_eq!(
+            P384Group::serialize_pk(&pk),
+            P384Group::serialize_pk(&P384Group::public_key(&opened_sk))
+        );
+
+        let pk_bytes = P384Group::serialize_pk(&pk);
+        let opened_pk = P384Group::deserialize_pk(&pk_bytes).expect("failed to deserialize pk");
+        assert_eq!(pk_bytes, P384Group::serialize_pk(&opened_pk));
+    }
+
+    #[test]
+    fn envelope_seal_open_round_trip() {
+        crate::envelope::tests::internal_seal_open_round_trip::<P384Group>();
+        crate::envelope::tests::external_seal_open_round_trip::<P384Group>();
+    }
+
+    #[test]
+    fn envelope_context_binding_mismatch() {
+        crate::envelope::tests::context_binding_mismatch::<P384Group>();
+    }
+
+    #[test]
+    fn envelope_context_binding_mismatch_external() {
+        crate::envelope::tests::context_binding_mismatch_external::<P384Group>();
+    }
+
+    #[test]
+    fn envelope_derive_key_distinct_labels() {
+        crate::envelope::tests::derive_key_distinct_labels::<P384Group>();
+    }
+
+    #[test]
+    fn diffie_hellman_agreement() {
+        let sk_a = P384Group::random_sk(&mut OsRng);
+        let sk_b = P384Group::random_sk(&mut OsRng);
+        let pk_a = P384Group::public_key(&sk_a);
+        let pk_b = P384Group::public_key(&sk_b);
+
+        assert_eq!(
+            P384Group::diffie_hellman(&sk_a, &pk_b),
+            P384Group::diffie_hellman(&sk_b, &pk_a)
+        );
+    }
+}
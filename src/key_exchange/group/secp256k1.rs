@@ -0,0 +1,128 @@
+// Copyright (c) Facebook, Inc. and its affiliates.
+//
+// This source code is licensed under both the MIT license found in the
+// LICENSE-MIT file in the root directory of this source tree and the Apache
+// License, Version 2.0 found in the LICENSE-APACHE file in the root directory
+// of this source tree.
+
+//! [`KeGroup`] implementation over secp256k1, gated behind the
+//! `secp256k1` feature.
+
+use generic_array::typenum::{U32, U33};
+use generic_array::GenericArray;
+use k256::Secp256k1;
+use rand::{CryptoRng, RngCore};
+
+use crate::errors::ProtocolError;
+use crate::key_exchange::group::nist;
+use crate::key_exchange::group::KeGroup;
+
+/// The secp256k1 group, as used by (e.g.) Bitcoin and Ethereum.
+pub struct Secp256k1Group;
+
+impl KeGroup for Secp256k1Group {
+    type Pk = elliptic_curve::PublicKey<Secp256k1>;
+    type Sk = elliptic_curve::NonZeroScalar<Secp256k1>;
+    type PkLen = U33;
+    type SkLen = U32;
+
+    fn random_sk<R: RngCore + CryptoRng>(rng: &mut R) -> Self::Sk {
+        nist::random_sk::<Secp256k1, R>(rng)
+    }
+
+    fn public_key(sk: &Self::Sk) -> Self::Pk {
+        nist::public_key(sk)
+    }
+
+    fn serialize_pk(pk: &Self::Pk) -> GenericArray<u8, Self::PkLen> {
+        nist::serialize_pk(pk)
+    }
+
+    fn deserialize_pk(bytes: &[u8]) -> Result<Self::Pk, ProtocolError> {
+        nist::deserialize_pk(bytes)
+    }
+
+    fn serialize_sk(sk: &Self::Sk) -> GenericArray<u8, Self::SkLen> {
+        nist::serialize_sk(sk)
+    }
+
+    fn deserialize_sk(bytes: &[u8]) -> Result<Self::Sk, ProtocolError> {
+        nist::deserialize_sk(bytes)
+    }
+
+    fn diffie_hellman(sk: &Self::Sk, pk: &Self::Pk) -> GenericArray<u8, Self::SkLen> {
+        nist::diffie_hellman(sk, pk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::OsRng;
+    use voprf::Ristretto255;
+
+    use super::*;
+    use crate::envelope::tests::TestOprfGroup;
+
+    // Ristretto255's 32-byte scalar matches Secp256k1Group::SkLen, so it can
+    // stand in as the test ciphersuite's OPRF group unchanged.
+    impl TestOprfGroup for Secp256k1Group {
+        type OprfGroup = Ristretto255;
+    }
+
+    // Round-trips `KeGroup::serialize_sk`/`serialize_pk` only; see
+    // `envelope_seal_open_round_trip` below for a real `Envelope::seal`/`open`
+    // round trip over this curve.
+    #[test]
+    fn keygroup_serialize_round_trip() {
+        let sk = Secp256k1Group::random_sk(&mut OsRng);
+        let pk = Secp256k1Group::public_key(&sk);
+
+        let sk_bytes = Secp256k1Group::serialize_sk(&sk);
+        let opened_sk =
+            Secp256k1Group::deserialize_sk(&sk_bytes).expect("failed to deserialize sk");
+        assert_eq!(sk_bytes, Secp256k1Group::serialize_sk(&opened_sk));
+        assert_eq!(
+            Secp256k1Group::serialize_pk(&pk),
+            Secp256k1Group::serialize_pk(&Secp256k1Group::public_key(&opened_sk))
+        );
+
+        let pk_bytes = Secp256k1Group::serialize_pk(&pk);
+        let opened_pk =
+            Secp256k1Group::deserialize_pk(&pk_bytes).expect("failed to deserialize pk");
+        assert_eq!(pk_bytes, Secp256k1Group::serialize_pk(&opened_pk));
+    }
+
+    #[test]
+    fn envelope_seal_open_round_trip() {
+        crate::envelope::tests::internal_seal_open_round_trip::<Secp256k1Group>();
+        crate::envelope::tests::external_seal_open_round_trip::<Secp256k1Group>();
+    }
+
+    #[test]
+    fn envelope_context_binding_mismatch() {
+        crate::envelope::tests::context_binding_mismatch::<Secp256k1Group>();
+    }
+
+    #[test]
+    fn envelope_context_binding_mismatch_external() {
+        crate::envelope::tests::context_binding_mismatch_external::<Secp256k1Group>();
+    }
+
+    #[test]
+    fn envelope_derive_key_distinct_labels() {
+        crate::envelope::tests::derive_key_distinct_labels::<Secp256k1Group>();
+    }
+
+    #[test]
+    fn diffie_hellman_agreement() {
+        let sk_a = Secp256k1Group::random_sk(&mut OsRng);
+        let sk_b = Secp256k1Group::random_sk(&mut OsRng);
+        let pk_a = Secp256k1Group::public_key(&sk_a);
+        let pk_b = Secp256k1Group::public_key(&sk_b);
+
+        assert_eq!(
+            Secp256k1Group::diffie_hellman(&sk_a, &pk_b),
+            Secp256k1Group::diffie_hellman(&sk_b, &pk_a)
+        );
+    }
+}
@@ -10,6 +10,7 @@ use sha2::{Digest, Sha256};
 
 pub trait SlowHash {
     fn hash(
+        &self,
         input: GenericArray<u8, <Sha256 as Digest>::OutputSize>,
     ) -> Result<Vec<u8>, InternalPakeError>;
 }
@@ -18,6 +19,7 @@ pub struct NoOpHash;
 
 impl SlowHash for NoOpHash {
     fn hash(
+        &self,
         input: GenericArray<u8, <Sha256 as Digest>::OutputSize>,
     ) -> Result<Vec<u8>, InternalPakeError> {
         Ok(input.to_vec())
@@ -27,12 +29,82 @@ impl SlowHash for NoOpHash {
 #[cfg(feature = "slow-hash")]
 impl SlowHash for scrypt::ScryptParams {
     fn hash(
+        &self,
         input: GenericArray<u8, <Sha256 as Digest>::OutputSize>,
     ) -> Result<Vec<u8>, InternalPakeError> {
-        let params = scrypt::ScryptParams::new(15, 8, 1).unwrap();
         let mut output = [0u8; 32];
-        scrypt::scrypt(&input, &[], &params, &mut output)
+        scrypt::scrypt(&input, &[], self, &mut output)
             .map_err(|_| InternalPakeError::SlowHashError)?;
         Ok(output.to_vec())
     }
-}
\ No newline at end of file
+}
+
+/// Parameters for the memory-hard [Argon2id](argon2::Algorithm::Argon2id)
+/// key-stretching function, tunable to the host's memory, CPU, and
+/// parallelism budget per the OWASP password-storage recommendations.
+#[cfg(feature = "slow-hash")]
+pub struct Argon2id(argon2::Params);
+
+#[cfg(feature = "slow-hash")]
+impl Argon2id {
+    /// Domain separator mixed into the input when deriving a per-call salt,
+    /// so the salt can never collide with the password it's paired with.
+    const SALT_DOMAIN: &'static [u8] = b"opaque-ke Argon2id salt";
+
+    /// Constructs the parameters from a memory size (in KiB), number of
+    /// iterations, and degree of parallelism.
+    pub fn new(
+        memory_size_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    ) -> Result<Self, InternalPakeError> {
+        argon2::Params::new(memory_size_kib, iterations, parallelism, Some(32))
+            .map(Self)
+            .map_err(|_| InternalPakeError::SlowHashError)
+    }
+}
+
+#[cfg(feature = "slow-hash")]
+impl SlowHash for Argon2id {
+    fn hash(
+        &self,
+        input: GenericArray<u8, <Sha256 as Digest>::OutputSize>,
+    ) -> Result<Vec<u8>, InternalPakeError> {
+        let argon2 = argon2::Argon2::new(
+            argon2::Algorithm::Argon2id,
+            argon2::Version::V0x13,
+            self.0.clone(),
+        );
+        // `hash_password_into` rejects salts shorter than
+        // `argon2::Params::MIN_SALT_LEN` (8 bytes), so derive one from the
+        // input rather than passing an empty slice.
+        let salt = Sha256::digest([Self::SALT_DOMAIN, input.as_slice()].concat());
+        let mut output = [0u8; 32];
+        argon2
+            .hash_password_into(&input, &salt, &mut output)
+            .map_err(|_| InternalPakeError::SlowHashError)?;
+        Ok(output.to_vec())
+    }
+}
+
+#[cfg(all(test, feature = "slow-hash"))]
+mod tests {
+    use super::*;
+
+    fn input(byte: u8) -> GenericArray<u8, <Sha256 as Digest>::OutputSize> {
+        GenericArray::from([byte; 32])
+    }
+
+    #[test]
+    fn argon2id_hash_is_deterministic_and_input_dependent() {
+        let argon2id = Argon2id::new(8, 1, 1).expect("failed to build Argon2id params");
+
+        let output_a = argon2id.hash(input(1)).expect("hash failed");
+        let output_a_again = argon2id.hash(input(1)).expect("hash failed");
+        let output_b = argon2id.hash(input(2)).expect("hash failed");
+
+        assert_eq!(output_a.len(), 32);
+        assert_eq!(output_a, output_a_again);
+        assert_ne!(output_a, output_b);
+    }
+}